@@ -0,0 +1,228 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// The bech32 data charset: each symbol encodes one 5-bit group.
+const CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Generator constants for the BCH checksum over GF(32).
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+/// Which transport an `Address` belongs to. This is the discriminant encoded in
+/// the human-readable prefix, so a UDP address and a Bluetooth address are no
+/// longer interchangeable integers that can collide in a mixed `Bucket`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Transport {
+    Udp,
+    Bluetooth
+}
+
+impl Transport {
+    /// The lowercase human-readable prefix that precedes the `1` separator.
+    pub fn prefix(&self) -> &'static str {
+        match *self {
+            Transport::Udp => "udp",
+            Transport::Bluetooth => "bt"
+        }
+    }
+
+    fn from_prefix(prefix: &str) -> Option<Transport> {
+        match prefix {
+            "udp" => Some(Transport::Udp),
+            "bt" => Some(Transport::Bluetooth),
+            _ => None
+        }
+    }
+}
+
+/// A self-describing node address: a transport discriminant plus the
+/// transport-specific payload bytes. Its `Display`/`FromStr` pair round-trips
+/// through a bech32-style text form — `udp1…` or `bt1…` — with a BCH checksum
+/// that rejects typos and truncations at parse time.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Address {
+    transport: Transport,
+    payload: Vec<u8>
+}
+
+impl Address {
+    pub fn new(transport: Transport, payload: Vec<u8>) -> Address {
+        Address {
+            transport: transport,
+            payload: payload
+        }
+    }
+
+    pub fn udp(payload: Vec<u8>) -> Address {
+        Address::new(Transport::Udp, payload)
+    }
+
+    pub fn bluetooth(payload: Vec<u8>) -> Address {
+        Address::new(Transport::Bluetooth, payload)
+    }
+
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+}
+
+/// Running modulo over GF(32) using the standard bech32 generator.
+fn polymod(values: &[u8]) -> u32 {
+    let mut checksum: u32 = 1;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = (checksum & 0x1ffffff) << 5 ^ (*value as u32);
+        for i in 0..5 {
+            if (top >> i) & 1 == 1 {
+                checksum ^= GENERATOR[i];
+            }
+        }
+    }
+    checksum
+}
+
+/// Expand the human-readable prefix into the high bits, a separator, then the
+/// low bits, matching the bech32 checksum domain.
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let bytes = hrp.as_bytes();
+    let mut expanded = Vec::with_capacity(bytes.len() * 2 + 1);
+    for byte in bytes {
+        expanded.push(byte >> 5);
+    }
+    expanded.push(0);
+    for byte in bytes {
+        expanded.push(byte & 31);
+    }
+    expanded
+}
+
+/// Compute the six check symbols appended to the data part.
+fn create_checksum(hrp: &str, data: &[u8]) -> Vec<u8> {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    let polymod = polymod(&values) ^ 1;
+    (0..6).map(|i| ((polymod >> (5 * (5 - i))) & 31) as u8).collect()
+}
+
+/// A valid encoding leaves the polynomial equal to the bech32 constant.
+fn verify_checksum(hrp: &str, data: &[u8]) -> bool {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    polymod(&values) == 1
+}
+
+/// Regroup bytes between bit widths (8 ↔ 5). Padding is only used on the
+/// encode side, where leftover bits are zero-filled into a final symbol.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let maxv = (1 << to) - 1;
+    let mut result = Vec::new();
+    for value in data {
+        let value = *value as u32;
+        if (value >> from) != 0 {
+            return None;
+        }
+        acc = (acc << from) | value;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push(((acc >> bits) & maxv) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            result.push(((acc << (to - bits)) & maxv) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & maxv) != 0 {
+        return None;
+    }
+    Some(result)
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hrp = self.transport.prefix();
+        let mut data = convert_bits(&self.payload, 8, 5, true)
+            .expect("8->5 regrouping with padding is infallible");
+        data.extend(create_checksum(hrp, &data));
+        let encoded: String = data.iter().map(|symbol| CHARSET[*symbol as usize] as char).collect();
+        write!(f, "{}1{}", hrp, encoded)
+    }
+}
+
+/// The ways a textual address can fail to parse.
+#[derive(Debug, PartialEq)]
+pub enum ParseAddressError {
+    MissingSeparator,
+    UnknownPrefix,
+    InvalidChar(char),
+    InvalidChecksum,
+    InvalidPayload
+}
+
+impl FromStr for Address {
+    type Err = ParseAddressError;
+
+    fn from_str(s: &str) -> Result<Address, ParseAddressError> {
+        let separator = s.rfind('1').ok_or(ParseAddressError::MissingSeparator)?;
+        let (hrp, data_part) = s.split_at(separator);
+        let transport = Transport::from_prefix(hrp).ok_or(ParseAddressError::UnknownPrefix)?;
+
+        let mut data = Vec::with_capacity(data_part.len() - 1);
+        for c in data_part[1..].chars() {
+            let symbol = CHARSET.iter()
+                .position(|candidate| *candidate as char == c)
+                .ok_or(ParseAddressError::InvalidChar(c))?;
+            data.push(symbol as u8);
+        }
+
+        if data.len() < 6 || !verify_checksum(hrp, &data) {
+            return Err(ParseAddressError::InvalidChecksum);
+        }
+
+        let payload = convert_bits(&data[..data.len() - 6], 5, 8, false)
+            .ok_or(ParseAddressError::InvalidPayload)?;
+        Ok(Address::new(transport, payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_text() {
+        let address = Address::udp(vec![0xde, 0xad, 0xbe, 0xef]);
+        let encoded = address.to_string();
+        assert!(encoded.starts_with("udp1"));
+        assert_eq!(encoded.parse::<Address>().unwrap(), address);
+    }
+
+    #[test]
+    fn prefix_carries_the_transport() {
+        let bt = Address::bluetooth(vec![1, 2, 3]).to_string();
+        assert!(bt.starts_with("bt1"));
+        assert_eq!(bt.parse::<Address>().unwrap().transport(), Transport::Bluetooth);
+    }
+
+    #[test]
+    fn rejects_a_corrupted_checksum() {
+        let encoded = Address::udp(vec![1, 2, 3, 4]).to_string();
+        let mut corrupted: Vec<char> = encoded.chars().collect();
+        let last = corrupted.len() - 1;
+        // Flip the final check symbol to something else in the charset.
+        corrupted[last] = if corrupted[last] == 'q' { 'p' } else { 'q' };
+        let corrupted: String = corrupted.into_iter().collect();
+        assert_eq!(corrupted.parse::<Address>(), Err(ParseAddressError::InvalidChecksum));
+    }
+
+    #[test]
+    fn rejects_an_unknown_prefix() {
+        assert_eq!("zz1qqqqqq".parse::<Address>(), Err(ParseAddressError::UnknownPrefix));
+    }
+}
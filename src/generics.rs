@@ -1,8 +1,10 @@
 use super::Address;
+use super::fnv::FnvBuildHasher;
 use std::collections::HashMap;
+use std::hash::BuildHasher;
 
-pub struct Bucket<N: Node> {
-    nodes: HashMap<Address, N>
+pub struct Bucket<N: Node, S = FnvBuildHasher> {
+    nodes: HashMap<Address, N, S>
 }
 
 pub struct UdpNode {
@@ -17,10 +19,16 @@ pub trait Node {
     fn address(&self) -> Address;
 }
 
-impl<N: Node> Bucket<N> {
-    pub fn new() -> Bucket<N> {
+impl<N: Node> Bucket<N, FnvBuildHasher> {
+    pub fn new() -> Bucket<N, FnvBuildHasher> {
+        Bucket::with_hasher(FnvBuildHasher::default())
+    }
+}
+
+impl<N: Node, S: BuildHasher> Bucket<N, S> {
+    pub fn with_hasher(hasher: S) -> Bucket<N, S> {
         Bucket {
-            nodes: HashMap::new()
+            nodes: HashMap::with_hasher(hasher)
         }
     }
 
@@ -47,13 +55,13 @@ impl UdpNode {
 
 impl Node for UdpNode {
     fn address(&self) -> Address {
-        self.address
+        self.address.clone()
     }
 }
 
 impl Node for BluetoothNode {
     fn address(&self) -> Address {
-        self.address
+        self.address.clone()
     }
 }
 
@@ -76,20 +84,20 @@ mod tests {
     #[test]
     fn insert_node() {
         let mut udp_bucket = Bucket::new();
-        let udp_node = UdpNode::new(0);
+        let udp_node = UdpNode::new(Address::udp(vec![0]));
         udp_bucket.insert(udp_node);
 
         let mut bluetooth_bucket = Bucket::new();
-        let bluetooth_node = BluetoothNode::new(1);
+        let bluetooth_node = BluetoothNode::new(Address::bluetooth(vec![1]));
         bluetooth_bucket.insert(bluetooth_node);
     }
 
     #[test]
     fn get_node() {
         let mut udp_bucket = Bucket::new();
-        let udp_node = UdpNode::new(0);
+        let udp_node = UdpNode::new(Address::udp(vec![0]));
         udp_bucket.insert(udp_node);
-        let node_ptr = udp_bucket.get(&0).unwrap();
+        let node_ptr = udp_bucket.get(&Address::udp(vec![0])).unwrap();
         node_ptr.send("hi!");
     }
 
@@ -97,10 +105,10 @@ mod tests {
     fn insert_another_kind_of_node() {
         let mut bucket = Bucket::new();
 
-        let udp_node = UdpNode::new(0);
+        let udp_node = UdpNode::new(Address::udp(vec![0]));
         bucket.insert(udp_node);
 
-        let bluetooth_node = BluetoothNode::new(0);
+        let bluetooth_node = BluetoothNode::new(Address::bluetooth(vec![0]));
         bucket.insert(bluetooth_node);
     }
 }
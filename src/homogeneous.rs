@@ -1,18 +1,26 @@
 use super::Address;
+use super::fnv::FnvBuildHasher;
 use std::collections::HashMap;
+use std::hash::BuildHasher;
 
-pub struct Bucket {
-    nodes: HashMap<Address, Node>
+pub struct Bucket<S = FnvBuildHasher> {
+    nodes: HashMap<Address, Node, S>
 }
 
 pub struct Node {
     address: Address
 }
 
-impl Bucket {
-    pub fn new() -> Bucket {
+impl Bucket<FnvBuildHasher> {
+    pub fn new() -> Bucket<FnvBuildHasher> {
+        Bucket::with_hasher(FnvBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> Bucket<S> {
+    pub fn with_hasher(hasher: S) -> Bucket<S> {
         Bucket {
-            nodes: HashMap::new()
+            nodes: HashMap::with_hasher(hasher)
         }
     }
 
@@ -21,7 +29,7 @@ impl Bucket {
     }
 
     pub fn insert(&mut self, node: Node) {
-        self.nodes.insert(node.address, node);
+        self.nodes.insert(node.address.clone(), node);
     }
 }
 
@@ -44,16 +52,16 @@ mod tests {
     #[test]
     fn insert_node() {
         let mut bucket = Bucket::new();
-        let node = Node::new(0);
+        let node = Node::new(Address::udp(vec![0]));
         bucket.insert(node);
     }
 
     #[test]
     fn get_node() {
         let mut bucket = Bucket::new();
-        let node = Node::new(0);
+        let node = Node::new(Address::udp(vec![0]));
         bucket.insert(node);
-        let node_ptr = bucket.get(&0).unwrap();
+        let node_ptr = bucket.get(&Address::udp(vec![0])).unwrap();
         node_ptr.send("hi!")
     }
 }
@@ -1,23 +1,130 @@
 use super::Address;
+use super::transport::Transport;
+use super::fnv::FnvBuildHasher;
 use std::collections::HashMap;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+#[cfg(windows)]
+use std::os::windows::io::RawSocket;
 
-pub struct Bucket {
-    nodes: HashMap<Address, Box<Node>>
+/// A node the `Bucket` can share across worker threads: connected (so it can be
+/// sent to), reference-counted, and `Send + Sync` so the transport work can be
+/// offloaded off the calling thread.
+pub type SharedNode = Arc<Connected<DynNode>>;
+
+/// The boxed, object-safe node stored behind a `Connected` token. Every node in
+/// a heterogeneous bucket reports failures through the same `SendError`.
+pub type DynNode = Box<dyn Node<Error = SendError> + Send + Sync>;
+
+/// Why a `send` failed, tagged with the address it failed against so the async
+/// layer can report exactly which nodes didn't make it.
+#[derive(Debug)]
+pub struct SendError {
+    pub address: Address,
+    pub reason: String
+}
+
+/// A readiness notification returned by `Bucket::poll_for_event`, carrying the
+/// address of the node whose descriptor reported incoming data.
+pub struct NodeEvent {
+    pub address: Address
 }
 
-impl Bucket {
-    pub fn new() -> Bucket {
+pub struct Bucket<S = FnvBuildHasher> {
+    backend: Box<dyn Transport>,
+    nodes: HashMap<Address, SharedNode, S>
+}
+
+impl Bucket<FnvBuildHasher> {
+    /// Build a bucket over a transport backend. Production passes a real
+    /// UDP/Bluetooth factory; tests pass a mock. The bucket's expected
+    /// transport is whatever the backend produces. Uses the fast FNV hasher
+    /// by default.
+    pub fn new(backend: Box<dyn Transport>) -> Bucket<FnvBuildHasher> {
+        Bucket::with_hasher(backend, FnvBuildHasher::default())
+    }
+}
+
+impl<S: BuildHasher> Bucket<S> {
+    /// Build a bucket with an explicit hasher, so callers that accept untrusted
+    /// addresses can opt back into a DoS-resistant `BuildHasher`.
+    pub fn with_hasher(backend: Box<dyn Transport>, hasher: S) -> Bucket<S> {
         Bucket {
-            nodes: HashMap::new()
+            backend: backend,
+            nodes: HashMap::with_hasher(hasher)
         }
     }
 
-    pub fn get(&self, address: &Address) -> Option<&Box<Node>> {
+    pub fn get(&self, address: &Address) -> Option<&SharedNode> {
         self.nodes.get(address)
     }
 
-    pub fn insert(&mut self, node: Box<Node>) {
-        self.nodes.insert(node.address(), node);
+    /// Make a node for `address` using the bucket's backend and insert it.
+    pub fn spawn(&mut self, address: Address) -> Result<(), Connected<DynNode>> {
+        let node = self.backend.make_node(address);
+        self.insert(node)
+    }
+
+    /// Insert a node, rejecting it if its address belongs to a different
+    /// transport than the bucket's backend produces — a `udp1…` node can't
+    /// slip into a Bluetooth bucket. The rejected node is handed back in `Err`
+    /// so the caller still owns it.
+    pub fn insert(&mut self, node: Connected<DynNode>) -> Result<(), Connected<DynNode>> {
+        if node.address().transport() != self.backend.kind() {
+            return Err(node);
+        }
+        self.nodes.insert(node.address(), Arc::new(node));
+        Ok(())
+    }
+
+    /// Offload one node's `send` onto a worker thread and hand back the handle
+    /// so the caller can join it for the result at its leisure.
+    pub fn send_async(&self, node: SharedNode, message: &str) -> JoinHandle<Result<(), SendError>> {
+        let message = message.to_owned();
+        thread::spawn(move || node.send(&message))
+    }
+
+    /// Fan a message out to every node concurrently and collect the results.
+    /// A failure on one node doesn't stall the rest: each runs on its own
+    /// thread and all of them are joined before returning.
+    pub fn broadcast(&self, message: &str) -> Vec<Result<(), SendError>> {
+        let handles: Vec<_> = self.nodes.values()
+            .map(|node| self.send_async(Arc::clone(node), message))
+            .collect();
+
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    }
+
+    /// Scan every registered node for readiness and return the first one (in
+    /// map order) whose `is_ready()` reports incoming data. Nodes without a
+    /// pollable descriptor (e.g. Bluetooth) keep the default `false` and so are
+    /// never reported; their descriptors are absent from `fds` as well.
+    pub fn poll_for_event(&self) -> Option<NodeEvent> {
+        self.nodes.values()
+            .filter(|node| node.is_ready())
+            .map(|node| NodeEvent { address: node.address() })
+            .next()
+    }
+
+    /// Every pollable descriptor currently registered, for handing to an
+    /// external epoll/kqueue/mio event loop. Nodes without one (e.g.
+    /// Bluetooth) are skipped.
+    #[cfg(unix)]
+    pub fn fds(&self) -> impl Iterator<Item = RawFd> + '_ {
+        self.nodes.values().filter_map(|node| node.as_raw_fd())
+    }
+
+    /// Windows counterpart to `fds`: every pollable descriptor currently
+    /// registered.
+    #[cfg(windows)]
+    pub fn sockets(&self) -> impl Iterator<Item = RawSocket> + '_ {
+        self.nodes.values().filter_map(|node| node.as_raw_socket())
     }
 }
 
@@ -29,9 +136,132 @@ pub struct BluetoothNode {
     address: Address
 }
 
+/// Proof that a node has been through `connect()`. Its field is private to
+/// this module, so nothing outside it can construct one — which is what
+/// actually gates `Node::send`, not `Connected` by itself. `Connected::send`
+/// is the only place a `ConnectToken` gets built.
+pub struct ConnectToken(());
+
 pub trait Node {
+    type Error;
+
     fn address(&self) -> Address;
-    fn send(&self, message: &str);
+
+    /// The transport send. Takes a `ConnectToken` that only `Connected::send`
+    /// can produce, so `UdpNode::new(addr).send("msg")` has no token to pass
+    /// and doesn't compile — a bare, unconnected node truly can't be sent to,
+    /// not just through a wrapper that happens not to expose the method.
+    fn send(&self, message: &str, proof: ConnectToken) -> Result<(), Self::Error>;
+
+    /// The underlying OS handle for transports that have one, so the bucket's
+    /// descriptors can be registered with an external event loop. Transports
+    /// without a pollable descriptor fall back to the default `None`.
+    #[cfg(unix)]
+    fn as_raw_fd(&self) -> Option<RawFd> {
+        None
+    }
+
+    #[cfg(windows)]
+    fn as_raw_socket(&self) -> Option<RawSocket> {
+        None
+    }
+
+    /// Whether this node currently has incoming data. The default is `false`,
+    /// which is what nodes without a pollable descriptor report.
+    fn is_ready(&self) -> bool {
+        false
+    }
+
+    /// Consume the node and hand back a `Connected` token. Because this takes
+    /// the boxed node by value, the original handle is gone afterwards.
+    ///
+    /// `Self: Sized` keeps this out of `Node`'s vtable (coercing `Box<Self>` to
+    /// `DynNode` is itself an unsizing coercion, which only applies to sized
+    /// `Self`) — it stays callable on every concrete node, just not through an
+    /// already-boxed trait object.
+    fn connect(self: Box<Self>) -> Connected<DynNode>
+        where Self: Sized + 'static + Send + Sync + Node<Error = SendError>
+    {
+        Connected { node: self }
+    }
+}
+
+/// Forward `Node` through a boxed trait object so `Connected<DynNode>` — which
+/// is `impl<N: Node> Connected<N>`, not a special case — has an `N: Node` to
+/// dispatch against. Without this, `DynNode` satisfies none of `Connected`'s
+/// bound and every method on a connected trait object fails to resolve.
+impl Node for DynNode {
+    type Error = SendError;
+
+    fn address(&self) -> Address {
+        (**self).address()
+    }
+
+    fn send(&self, message: &str, proof: ConnectToken) -> Result<(), SendError> {
+        (**self).send(message, proof)
+    }
+
+    #[cfg(unix)]
+    fn as_raw_fd(&self) -> Option<RawFd> {
+        (**self).as_raw_fd()
+    }
+
+    #[cfg(windows)]
+    fn as_raw_socket(&self) -> Option<RawSocket> {
+        (**self).as_raw_socket()
+    }
+
+    fn is_ready(&self) -> bool {
+        (**self).is_ready()
+    }
+}
+
+/// A node that has been through `connect`. Every other `Node` method is plain,
+/// but reaching `send` also requires a `ConnectToken`, which only the `send`
+/// method below ever constructs — so a disconnected node simply can't be sent
+/// to. `Connected` is neither `Copy` nor `Clone`, so `disconnect` — which takes
+/// `self` by value — leaves no live token behind.
+pub struct Connected<N> {
+    node: N
+}
+
+impl<N: Node> Connected<N> {
+    pub fn address(&self) -> Address {
+        self.node.address()
+    }
+
+    pub fn send(&self, message: &str) -> Result<(), N::Error> {
+        self.node.send(message, ConnectToken(()))
+    }
+
+    #[cfg(unix)]
+    pub fn as_raw_fd(&self) -> Option<RawFd> {
+        self.node.as_raw_fd()
+    }
+
+    #[cfg(windows)]
+    pub fn as_raw_socket(&self) -> Option<RawSocket> {
+        self.node.as_raw_socket()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.node.is_ready()
+    }
+
+    /// Consume the token and return the bare node. A later `send` against the
+    /// recovered handle is a compile error, not a runtime one.
+    pub fn disconnect(self) -> N {
+        self.node
+    }
+}
+
+/// Identifies a `Connected` node by its address rather than requiring the
+/// wrapped node itself to be `Debug` — needed so `Bucket::insert`'s
+/// `Result<(), Connected<DynNode>>` can be `unwrap()`ed in tests.
+impl<N: Node> fmt::Debug for Connected<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Connected({:?})", self.node.address())
+    }
 }
 
 impl UdpNode {
@@ -51,51 +281,146 @@ impl BluetoothNode {
 }
 
 impl Node for UdpNode {
+    type Error = SendError;
+
     fn address(&self) -> Address {
-        self.address
+        self.address.clone()
     }
 
-    fn send(&self, message: &str) {
+    fn send(&self, message: &str, _proof: ConnectToken) -> Result<(), SendError> {
         println!("Sending via UDP: {}", message);
+        Ok(())
     }
 }
 
 impl Node for BluetoothNode {
+    type Error = SendError;
+
     fn address(&self) -> Address {
-        self.address
+        self.address.clone()
     }
 
-    fn send(&self, message: &str) {
+    fn send(&self, message: &str, _proof: ConnectToken) -> Result<(), SendError> {
         println!("Sending via Bluetooth: {}", message);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::transport::{UdpTransport, BluetoothTransport};
+
+    /// A node with a real (if fake) descriptor, so `poll_for_event` and `fds`
+    /// have something other than the always-unready `UdpNode`/`BluetoothNode`
+    /// to report on.
+    struct ReadyNode {
+        address: Address
+    }
+
+    impl ReadyNode {
+        fn new(address: Address) -> ReadyNode {
+            ReadyNode { address: address }
+        }
+    }
+
+    impl Node for ReadyNode {
+        type Error = SendError;
+
+        fn address(&self) -> Address {
+            self.address.clone()
+        }
+
+        fn send(&self, _message: &str, _proof: ConnectToken) -> Result<(), SendError> {
+            Ok(())
+        }
+
+        #[cfg(unix)]
+        fn as_raw_fd(&self) -> Option<RawFd> {
+            Some(9)
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+    }
 
     #[test]
     fn insert_node() {
-        let mut bucket = Bucket::new();
+        let mut udp_bucket = Bucket::new(Box::new(UdpTransport::new()));
+        let mut bluetooth_bucket = Bucket::new(Box::new(BluetoothTransport::new()));
 
-        let udp_node = UdpNode::new(0);
-        let bluetooth_node = BluetoothNode::new(1);
+        let udp_node = UdpNode::new(Address::udp(vec![0]));
+        let bluetooth_node = BluetoothNode::new(Address::bluetooth(vec![1]));
 
-        bucket.insert(Box::new(udp_node));
-        bucket.insert(Box::new(bluetooth_node));
+        udp_bucket.insert(Box::new(udp_node).connect()).unwrap();
+        bluetooth_bucket.insert(Box::new(bluetooth_node).connect()).unwrap();
     }
 
     #[test]
     fn get_node() {
-        let mut bucket = Bucket::new();
+        let mut bucket = Bucket::new(Box::new(UdpTransport::new()));
+
+        let udp_node = UdpNode::new(Address::udp(vec![0]));
+        bucket.insert(Box::new(udp_node).connect()).unwrap();
+
+        bucket.get(&Address::udp(vec![0])).unwrap().send("Sending with UDP node").unwrap();
+    }
+
+    #[test]
+    fn insert_rejects_a_mismatched_transport() {
+        let mut bucket = Bucket::new(Box::new(UdpTransport::new()));
+        let bluetooth_node = BluetoothNode::new(Address::bluetooth(vec![1]));
+
+        assert!(bucket.insert(Box::new(bluetooth_node).connect()).is_err());
+    }
+
+    #[test]
+    fn disconnect_returns_the_bare_node() {
+        let connected = Box::new(UdpNode::new(Address::udp(vec![0]))).connect();
+        let node = connected.disconnect();
+        // `connected` is consumed here; only the recovered node remains.
+        assert_eq!(node.address(), Address::udp(vec![0]));
+    }
+
+    #[test]
+    fn broadcast_reaches_every_node() {
+        let mut bucket = Bucket::new(Box::new(UdpTransport::new()));
+        bucket.insert(Box::new(UdpNode::new(Address::udp(vec![0]))).connect()).unwrap();
+        bucket.insert(Box::new(UdpNode::new(Address::udp(vec![1]))).connect()).unwrap();
 
-        let udp_node = UdpNode::new(0);
-        let bluetooth_node = BluetoothNode::new(1);
+        let results = bucket.broadcast("fan out");
+        assert_eq!(results.len(), 2);
+        assert!(results.into_iter().all(|result| result.is_ok()));
+    }
 
-        bucket.insert(Box::new(udp_node));
-        bucket.insert(Box::new(bluetooth_node));
+    #[test]
+    fn poll_for_event_skips_unpollable_nodes() {
+        let mut bucket = Bucket::new(Box::new(BluetoothTransport::new()));
+        bucket.insert(Box::new(BluetoothNode::new(Address::bluetooth(vec![1]))).connect()).unwrap();
+
+        // A Bluetooth node has no pollable descriptor, so nothing is ready.
+        assert!(bucket.poll_for_event().is_none());
+    }
+
+    #[test]
+    fn poll_for_event_reports_a_ready_node() {
+        let mut bucket = Bucket::new(Box::new(UdpTransport::new()));
+        bucket.insert(Box::new(ReadyNode::new(Address::udp(vec![9]))).connect()).unwrap();
+
+        let event = bucket.poll_for_event().expect("the ready node should be reported");
+        assert_eq!(event.address, Address::udp(vec![9]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fds_enumerates_pollable_descriptors() {
+        let mut bucket = Bucket::new(Box::new(UdpTransport::new()));
+        bucket.insert(Box::new(UdpNode::new(Address::udp(vec![0]))).connect()).unwrap();
+        bucket.insert(Box::new(ReadyNode::new(Address::udp(vec![9]))).connect()).unwrap();
 
-        bucket.get(&0).unwrap().send("Sending with UDP node");
-        bucket.get(&1).unwrap().send("Sending with Bluetooth node");
+        // UdpNode has no real descriptor; only the node that reports one shows up.
+        let fds: Vec<RawFd> = bucket.fds().collect();
+        assert_eq!(fds, vec![9]);
     }
 }
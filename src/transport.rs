@@ -0,0 +1,134 @@
+use super::Address;
+use super::address::Transport as TransportKind;
+use super::trait_objects::{Node, DynNode, Connected, ConnectToken, SendError};
+use std::sync::{Arc, Mutex};
+
+/// A factory for the nodes a `Bucket` holds. Production code hands a `Bucket`
+/// a real UDP or Bluetooth backend; tests hand it a `MockTransport`. The rest
+/// of the bucket API is identical either way — only the source of nodes changes.
+pub trait Transport {
+    /// Which transport kind this backend produces, so a `Bucket` can reject a
+    /// node whose address belongs to a different transport.
+    fn kind(&self) -> TransportKind;
+
+    /// Build a live node for `address`, ready to be inserted into a bucket.
+    ///
+    /// Returns a `Connected<DynNode>` rather than a bare `DynNode`: the
+    /// factory calls `connect()` itself so a `Transport` can only ever hand a
+    /// `Bucket` nodes that are already past the typestate gate, and `insert`
+    /// never has to deal with (or forget to handle) an unconnected node.
+    fn make_node(&self, address: Address) -> Connected<DynNode>;
+}
+
+/// Backend that produces real UDP nodes.
+pub struct UdpTransport;
+
+impl UdpTransport {
+    pub fn new() -> UdpTransport {
+        UdpTransport
+    }
+}
+
+impl Transport for UdpTransport {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Udp
+    }
+
+    fn make_node(&self, address: Address) -> Connected<DynNode> {
+        Box::new(super::trait_objects::UdpNode::new(address)).connect()
+    }
+}
+
+/// Backend that produces real Bluetooth nodes.
+pub struct BluetoothTransport;
+
+impl BluetoothTransport {
+    pub fn new() -> BluetoothTransport {
+        BluetoothTransport
+    }
+}
+
+impl Transport for BluetoothTransport {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Bluetooth
+    }
+
+    fn make_node(&self, address: Address) -> Connected<DynNode> {
+        Box::new(super::trait_objects::BluetoothNode::new(address)).connect()
+    }
+}
+
+/// A fake backend for tests. Every node it makes records its `send` calls into
+/// a shared log instead of touching the network or Bluetooth stack, so a test
+/// can assert exactly which messages were dispatched.
+pub struct MockTransport {
+    kind: TransportKind,
+    pub sent: Arc<Mutex<Vec<String>>>
+}
+
+impl MockTransport {
+    pub fn new(kind: TransportKind) -> MockTransport {
+        MockTransport {
+            kind: kind,
+            sent: Arc::new(Mutex::new(Vec::new()))
+        }
+    }
+
+    /// A handle onto the shared log, cloned so the test keeps a view after the
+    /// transport has been moved into a `Bucket`.
+    pub fn sent(&self) -> Arc<Mutex<Vec<String>>> {
+        Arc::clone(&self.sent)
+    }
+}
+
+impl Transport for MockTransport {
+    fn kind(&self) -> TransportKind {
+        self.kind
+    }
+
+    fn make_node(&self, address: Address) -> Connected<DynNode> {
+        Box::new(MockNode {
+            address: address,
+            sent: Arc::clone(&self.sent)
+        }).connect()
+    }
+}
+
+struct MockNode {
+    address: Address,
+    sent: Arc<Mutex<Vec<String>>>
+}
+
+impl Node for MockNode {
+    type Error = SendError;
+
+    fn address(&self) -> Address {
+        self.address.clone()
+    }
+
+    fn send(&self, message: &str, _proof: ConnectToken) -> Result<(), SendError> {
+        self.sent.lock().unwrap().push(message.to_owned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::trait_objects::Bucket;
+
+    #[test]
+    fn mock_transport_records_dispatched_messages() {
+        let transport = MockTransport::new(TransportKind::Udp);
+        let sent = transport.sent();
+
+        let mut bucket = Bucket::new(Box::new(transport));
+        bucket.spawn(Address::udp(vec![0])).unwrap();
+        bucket.spawn(Address::udp(vec![1])).unwrap();
+        bucket.broadcast("hello");
+
+        let mut sent = sent.lock().unwrap().clone();
+        sent.sort();
+        assert_eq!(sent, vec!["hello".to_string(), "hello".to_string()]);
+    }
+}